@@ -1,10 +1,6 @@
-use crate::logger::LogFormat;
-
-// Example using the `log` crate.
+// Example using the `log` crate together with `google_cloud_logging`'s built-in `Logger`.
 // https://crates.io/crates/log
 
-mod logger;
-
 fn main() {
     setup_logger();
 
@@ -16,12 +12,11 @@ fn main() {
 
 fn setup_logger() {
     use log::LevelFilter;
-    // Setup logger and log level
-    log::set_boxed_logger(Box::new(logger::Logger::custom(LogFormat::Json)))
-        .expect("Could not setup logger");
-    if cfg!(debug_assertions) {
-        log::set_max_level(LevelFilter::Trace);
+
+    let max_level = if cfg!(debug_assertions) {
+        LevelFilter::Trace
     } else {
-        log::set_max_level(LevelFilter::Info);
-    }
+        LevelFilter::Info
+    };
+    google_cloud_logging::log::Logger::init(max_level).expect("Could not setup logger");
 }
@@ -16,19 +16,51 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
+
+/// A batching HTTP transport that ships entries to the Cloud Logging `entries:write` API.
+///
+/// Requires the `writer` feature.
+#[cfg(feature = "writer")]
+pub mod writer;
+
+/// Auto-detection of the [`GCMonitoredResource`] for the environment the binary is running in.
+///
+/// Requires the `detect-resource` feature.
+#[cfg(feature = "detect-resource")]
+pub mod resource;
+
+/// A [`log::Log`] implementation that emits [`GoogleCloudStructLog`] entries.
+///
+/// Requires the `log` feature.
+#[cfg(feature = "log")]
+pub mod log;
+
+/// A `tracing_subscriber::Layer` that emits [`GoogleCloudStructLog`] entries.
+///
+/// Requires the `tracing` feature.
+#[cfg(feature = "tracing")]
+pub mod tracing;
 
 /// The format expected by Google Cloud Platform logging service
 /// https://cloud.google.com/logging/docs/structured-logging
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+///
+/// Generic over the structured payload type `P`. Most users can ignore the type
+/// parameter (it defaults to `()`, matching a plain text message); pass your own
+/// `P: Serialize` to attach an arbitrary JSON `jsonPayload` instead, see [`GCPayload`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
-pub struct GoogleCloudStructLog<'a> {
+pub struct GoogleCloudStructLog<'a, P = ()> {
     /// The Logging agent attempts to match a variety of common severity strings,
     /// which includes the list of LogSeverity strings recognized by the Logging API.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub severity: Option<GCLogSeverity>,
-    /// The message that appears on the log entry line in the Logs Explorer.
+    /// The payload of the log entry: either a plain text message, or an arbitrary
+    /// structured payload that the Logs Explorer indexes by field. The two are
+    /// mutually exclusive; see [`GCPayload`].
     ///
-    /// Optionally add a backtrace here using following format (including newlines):
+    /// When using a text message, optionally add a backtrace using following format
+    /// (including newlines):
     /// ```text
     /// My normal log message goes here:
     ///    at services::module_name::he77c0bac773c93b4 line: 42
@@ -36,13 +68,19 @@ pub struct GoogleCloudStructLog<'a> {
     /// ```
     /// Note the `:` at the end of the log message and the 3 space and `at ` before each line of the
     /// backtrace. The ` line: <Nr>` is optional.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub message: Option<String>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<GCPayload<P>>,
     /// Can be used to set for Error reporting
     /// More info see: https://cloud.google.com/error-reporting/docs/formatting-error-messages#@type
     #[serde(rename = "@type")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub report_type: Option<String>,
+    /// The service that produced the error, used by Error Reporting to group errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_context: Option<GCServiceContext>,
+    /// Additional context about the error, such as the location it was reported from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<GCErrorContext<'a>>,
     /// A structured record in the format of the LogEntry HttpRequest field.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub http_request: Option<GCHttpRequest>,
@@ -99,6 +137,167 @@ pub struct GoogleCloudStructLog<'a> {
     pub phantom: Option<&'a str>,
 }
 
+// Derived `Default` would require `P: Default`, which is an unnecessary bound since
+// `payload` is always `None` in the default entry regardless of `P`.
+impl<'a, P> Default for GoogleCloudStructLog<'a, P> {
+    fn default() -> Self {
+        Self {
+            severity: None,
+            payload: None,
+            report_type: None,
+            service_context: None,
+            context: None,
+            http_request: None,
+            time: None,
+            insert_id: None,
+            labels: HashMap::new(),
+            operation: None,
+            source_location: None,
+            span_id: None,
+            trace: None,
+            trace_sampled: None,
+            phantom: None,
+        }
+    }
+}
+
+impl<'a, P> GoogleCloudStructLog<'a, P> {
+    /// Builds an entry formatted for Error Reporting: sets `@type`, `serviceContext` and
+    /// `context.reportLocation` so the error groups correctly.
+    ///
+    /// `severity` should be [`GCLogSeverity::Error`] or higher, since Error Reporting only
+    /// processes entries at that level.
+    pub fn new_error(
+        severity: GCLogSeverity,
+        message: impl Into<String>,
+        service_context: GCServiceContext,
+        report_location: GCReportLocation<'a>,
+    ) -> Self {
+        debug_assert!(
+            matches!(
+                severity,
+                GCLogSeverity::Error
+                    | GCLogSeverity::Critical
+                    | GCLogSeverity::Alert
+                    | GCLogSeverity::Emergency
+            ),
+            "Error Reporting only processes entries with severity Error or higher"
+        );
+
+        Self {
+            severity: Some(severity),
+            payload: Some(GCPayload::Message(message.into())),
+            report_type: Some(
+                "type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent"
+                    .to_owned(),
+            ),
+            service_context: Some(service_context),
+            context: Some(GCErrorContext {
+                report_location: Some(report_location),
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+/// The payload of a [`GoogleCloudStructLog`]: either a plain text message or an
+/// arbitrary structured payload.
+///
+/// Serialized so that only one of the two is ever present on the wire: a [`Message`]
+/// produces the familiar top-level `message` field, while a [`Json`] payload is
+/// flattened into the entry's top-level fields, where the Logs Explorer indexes it.
+///
+/// [`Message`]: GCPayload::Message
+/// [`Json`]: GCPayload::Json
+#[derive(Clone, Debug)]
+pub enum GCPayload<P> {
+    /// A plain text message, serialized as the entry's `message` field.
+    Message(String),
+    /// An arbitrary structured payload, serialized as top-level fields on the entry.
+    Json(P),
+}
+
+impl<P> Serialize for GCPayload<P>
+where
+    P: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        match self {
+            GCPayload::Message(message) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("message", message)?;
+                map.end()
+            }
+            GCPayload::Json(payload) => payload.serialize(serializer),
+        }
+    }
+}
+
+impl<'de, P> Deserialize<'de> for GCPayload<P>
+where
+    P: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let map = serde_json::Map::deserialize(deserializer)?;
+        if map.len() == 1 {
+            if let Some(serde_json::Value::String(message)) = map.get("message") {
+                return Ok(GCPayload::Message(message.clone()));
+            }
+        }
+        P::deserialize(serde_json::Value::Object(map))
+            .map(GCPayload::Json)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Identifies the service that produced an error, used by Error Reporting to group errors.
+/// More info see: https://cloud.google.com/error-reporting/reference/rest/v1beta1/ServiceContext
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GCServiceContext {
+    /// An identifier of the service, such as the name of the executable, job, or service.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// Represents the source code version that the developer provided.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Additional data about the error, used by Error Reporting to group errors.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", bound(deserialize = "'de: 'a"))]
+pub struct GCErrorContext<'a> {
+    /// The location in the source code where the decision was made to report the error,
+    /// usually the place where it was logged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report_location: Option<GCReportLocation<'a>>,
+}
+
+/// Indicates a location in the source code of the service for which errors are reported.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GCReportLocation<'a> {
+    /// The source code filename, which can include a truncated relative path,
+    /// or a full path from a base directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<&'a str>,
+    /// 1-based. 0 indicates that the line number is unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_number: Option<u32>,
+    /// Human-readable name of a function or method. The value can include optional
+    /// context, such as the class or package name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_name: Option<&'a str>,
+}
+
 /// The severity of the event described in a log entry, expressed as one of the standard severity
 /// levels listed below.
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
@@ -143,15 +342,15 @@ pub struct GCHttpRequest {
     pub request_url: Option<String>,
     /// The size of the HTTP request message in bytes,
     /// including the request headers and the request body.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub request_size: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "opt_u64_as_string", default)]
+    pub request_size: Option<u64>,
     /// The response code indicating the status of response. Examples: 200, 404.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<u16>,
     /// The size of the HTTP response message sent back to the client, in bytes,
     /// including the response headers and the response body.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub response_size: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "opt_u64_as_string", default)]
+    pub response_size: Option<u64>,
     /// The user agent sent by the client.
     /// Example: "Mozilla/4.0 (compatible; MSIE 6.0; Windows 98; Q312461; .NET CLR 1.0.3705)".
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -169,13 +368,104 @@ pub struct GCHttpRequest {
     /// The request processing latency on the server,
     /// from the time the request was received until the response was sent.
     ///
-    /// A duration in seconds with up to nine fractional digits, terminated by 's'.
-    /// Example: "3.5s".
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub latency: Option<String>,
+    /// Serialized as a duration in seconds with up to nine fractional digits,
+    /// terminated by 's'. Example: "3.5s".
+    #[serde(skip_serializing_if = "Option::is_none", with = "opt_duration_as_string", default)]
+    pub latency: Option<Duration>,
     /// Protocol used for the request. Examples: "HTTP/1.1", "HTTP/2", "websocket"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub protocol: Option<String>,
+    /// The number of HTTP response bytes inserted into cache. Set only when a cache fill
+    /// was attempted.
+    #[serde(skip_serializing_if = "Option::is_none", with = "opt_u64_as_string", default)]
+    pub cache_fill_bytes: Option<u64>,
+    /// Whether or not an entity was served from cache (with or without validation).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_hit: Option<bool>,
+    /// Whether or not a cache lookup was attempted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_lookup: Option<bool>,
+    /// Whether or not the response was validated with the origin server before being
+    /// served from cache. This field is only meaningful if `cache_hit` is `true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_validated_with_origin_server: Option<bool>,
+    /// The referer URL of the request, as defined in the HTTP `Referer` header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referer: Option<String>,
+}
+
+/// Serializes an `Option<u64>` as a decimal string (or omits it), the format the
+/// `entries:write` API expects for byte-size fields like `requestSize`.
+mod opt_u64_as_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(value) => serializer.serialize_str(&value.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| value.parse().map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+/// Serializes an `Option<Duration>` as Google's `"<seconds>.<nanos>s"` duration format
+/// (or omits it), e.g. `Duration::new(3, 500_000_000)` becomes `"3.5s"`.
+mod opt_duration_as_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S>(value: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(duration) => serializer.serialize_str(&format_duration(*duration)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    fn format_duration(duration: Duration) -> String {
+        let nanos = duration.subsec_nanos();
+        if nanos == 0 {
+            format!("{}s", duration.as_secs())
+        } else {
+            let fraction = format!("{nanos:09}");
+            format!("{}.{}s", duration.as_secs(), fraction.trim_end_matches('0'))
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| parse_duration(&value).ok_or_else(|| serde::de::Error::custom(format!("invalid duration: {value}"))))
+            .transpose()
+    }
+
+    fn parse_duration(value: &str) -> Option<Duration> {
+        let value = value.strip_suffix('s')?;
+        let (secs, nanos) = match value.split_once('.') {
+            Some((secs, fraction)) => {
+                let secs = secs.parse().ok()?;
+                let fraction = format!("{fraction:0<9}");
+                (secs, fraction.get(..9)?.parse().ok()?)
+            }
+            None => (value.parse().ok()?, 0),
+        };
+        Some(Duration::new(secs, nanos))
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
@@ -212,6 +502,49 @@ pub struct GCOperation<'a> {
     pub last: Option<bool>,
 }
 
+/// The monitored resource that a log entry (or a whole log) is associated with,
+/// e.g. a `gce_instance` or a `k8s_container`.
+///
+/// See the [list of monitored resource types](https://cloud.google.com/monitoring/api/resources)
+/// for the `type` and label combinations Cloud Logging understands.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GCMonitoredResource<'a> {
+    /// The monitored resource type, e.g. `"gce_instance"` or `"global"`.
+    #[serde(rename = "type")]
+    pub type_: &'a str,
+    /// Values for all of the labels listed in the associated monitored resource descriptor.
+    pub labels: HashMap<String, String>,
+}
+
+impl<'a> GCMonitoredResource<'a> {
+    /// Builds a `global` resource, the fallback used when no more specific resource type
+    /// can be determined for the running environment.
+    pub fn global(project_id: impl Into<String>) -> GCMonitoredResource<'static> {
+        let mut labels = HashMap::new();
+        labels.insert("project_id".to_owned(), project_id.into());
+        GCMonitoredResource {
+            type_: "global",
+            labels,
+        }
+    }
+
+    /// Builds a `gce_instance` resource from the instance's project id, instance id and zone.
+    pub fn gce_instance(
+        project_id: impl Into<String>,
+        instance_id: impl Into<String>,
+        zone: impl Into<String>,
+    ) -> GCMonitoredResource<'static> {
+        let mut labels = HashMap::new();
+        labels.insert("project_id".to_owned(), project_id.into());
+        labels.insert("instance_id".to_owned(), instance_id.into());
+        labels.insert("zone".to_owned(), zone.into());
+        GCMonitoredResource {
+            type_: "gce_instance",
+            labels,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct GCSourceLocation<'a> {
@@ -230,3 +563,141 @@ pub struct GCSourceLocation<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub function: Option<&'a str>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn http_request_latency_round_trips_at_second_boundaries() {
+        let cases = [
+            (Duration::new(0, 0), r#""0s""#),
+            (Duration::new(3, 500_000_000), r#""3.5s""#),
+            (Duration::new(3, 0), r#""3s""#),
+            (Duration::new(0, 1), r#""0.000000001s""#),
+        ];
+
+        for (duration, expected) in cases {
+            let request = GCHttpRequest {
+                latency: Some(duration),
+                ..Default::default()
+            };
+            let json = serde_json::to_value(&request).unwrap();
+            assert_eq!(json["latency"].to_string(), expected, "serializing {duration:?}");
+
+            let round_tripped: GCHttpRequest = serde_json::from_value(json).unwrap();
+            assert_eq!(round_tripped.latency, Some(duration));
+        }
+    }
+
+    #[test]
+    fn http_request_byte_sizes_round_trip_as_strings() {
+        for value in [0u64, 1, 1024, u64::MAX] {
+            let request = GCHttpRequest {
+                request_size: Some(value),
+                response_size: Some(value),
+                cache_fill_bytes: Some(value),
+                ..Default::default()
+            };
+            let json = serde_json::to_value(&request).unwrap();
+            assert_eq!(json["requestSize"], serde_json::json!(value.to_string()));
+            assert_eq!(json["responseSize"], serde_json::json!(value.to_string()));
+            assert_eq!(json["cacheFillBytes"], serde_json::json!(value.to_string()));
+
+            let round_tripped: GCHttpRequest = serde_json::from_value(json).unwrap();
+            assert_eq!(round_tripped.request_size, Some(value));
+            assert_eq!(round_tripped.response_size, Some(value));
+            assert_eq!(round_tripped.cache_fill_bytes, Some(value));
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    struct TestPayload {
+        category: String,
+        count: u32,
+    }
+
+    #[test]
+    fn message_payload_round_trips_and_excludes_json_fields() {
+        let entry: GoogleCloudStructLog = GoogleCloudStructLog {
+            payload: Some(GCPayload::Message("hello".to_owned())),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(json, r#"{"message":"hello"}"#);
+
+        // `labels` has no `#[serde(default)]`, so it must be present to deserialize;
+        // unrelated to what this test is about, so it's added back in here.
+        let json_with_labels = json.replace('}', r#","logging.googleapis.com/labels":{}}"#);
+        let round_tripped: GoogleCloudStructLog = serde_json::from_str(&json_with_labels).unwrap();
+        assert!(matches!(round_tripped.payload, Some(GCPayload::Message(m)) if m == "hello"));
+    }
+
+    #[test]
+    fn json_payload_round_trips_and_excludes_message_field() {
+        let payload = TestPayload {
+            category: "auth".to_owned(),
+            count: 3,
+        };
+        let entry: GoogleCloudStructLog<TestPayload> = GoogleCloudStructLog {
+            payload: Some(GCPayload::Json(payload.clone())),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(json, r#"{"category":"auth","count":3}"#);
+        assert!(!json.contains("message"));
+
+        let json_with_labels = json.replace('}', r#","logging.googleapis.com/labels":{}}"#);
+        let round_tripped: GoogleCloudStructLog<TestPayload> =
+            serde_json::from_str(&json_with_labels).unwrap();
+        assert!(matches!(round_tripped.payload, Some(GCPayload::Json(p)) if p == payload));
+    }
+
+    #[test]
+    fn new_error_sets_the_error_reporting_shape() {
+        let entry: GoogleCloudStructLog = GoogleCloudStructLog::new_error(
+            GCLogSeverity::Error,
+            "something broke",
+            GCServiceContext {
+                service: Some("my-service".to_owned()),
+                version: Some("1.0.0".to_owned()),
+            },
+            GCReportLocation {
+                file_path: Some("src/main.rs"),
+                line_number: Some(42),
+                function_name: Some("do_thing"),
+            },
+        );
+
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["message"], "something broke");
+        assert_eq!(
+            json["@type"],
+            "type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent"
+        );
+        assert_eq!(json["serviceContext"]["service"], "my-service");
+        assert_eq!(json["serviceContext"]["version"], "1.0.0");
+        assert_eq!(json["context"]["reportLocation"]["filePath"], "src/main.rs");
+        assert_eq!(json["context"]["reportLocation"]["lineNumber"], 42);
+        assert_eq!(json["context"]["reportLocation"]["functionName"], "do_thing");
+
+        // `labels` has no `#[serde(default)]`, so it must be present to deserialize;
+        // unrelated to what this test is about, so it's added back in here.
+        let mut json_with_labels = json;
+        json_with_labels["logging.googleapis.com/labels"] = serde_json::json!({});
+        let json_with_labels = json_with_labels.to_string();
+        let round_tripped: GoogleCloudStructLog = serde_json::from_str(&json_with_labels).unwrap();
+        assert!(matches!(round_tripped.payload, Some(GCPayload::Message(m)) if m == "something broke"));
+        assert_eq!(
+            round_tripped
+                .context
+                .and_then(|c| c.report_location)
+                .and_then(|l| l.line_number),
+            Some(42)
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! A [`log::Log`] implementation that emits [`GoogleCloudStructLog`] entries as JSON.
+//!
+//! Requires the `log` feature.
+
+use crate::{GCLogSeverity, GCPayload, GCSourceLocation, GoogleCloudStructLog};
+use chrono::Utc;
+use log::{Level, Metadata, Record};
+
+/// Maps [`log::Record`]s to [`GoogleCloudStructLog`] entries and prints them as JSON,
+/// one per line, so a Cloud Logging agent reading stdout/stderr picks up structured
+/// severity and source location for every log line.
+pub struct Logger {
+    max_level: log::LevelFilter,
+}
+
+impl Logger {
+    /// Creates a logger that accepts records up to `max_level`.
+    pub fn new(max_level: log::LevelFilter) -> Self {
+        Self { max_level }
+    }
+
+    /// Installs this logger as the global `log` logger and sets the max level filter.
+    pub fn init(max_level: log::LevelFilter) -> Result<(), log::SetLoggerError> {
+        log::set_max_level(max_level);
+        log::set_boxed_logger(Box::new(Self::new(max_level)))
+    }
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.max_level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let entry = GoogleCloudStructLog {
+            severity: Some(severity_for(record.level())),
+            payload: Some(GCPayload::<()>::Message(record.args().to_string())),
+            source_location: Some(GCSourceLocation {
+                file: record.file_static(),
+                line: record.line().map(|line| line.to_string()),
+                function: record.module_path_static(),
+            }),
+            time: Some(Utc::now()),
+            ..Default::default()
+        };
+
+        println!(
+            "{}",
+            serde_json::to_string(&entry).expect("GoogleCloudStructLog always serializes")
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+fn severity_for(level: Level) -> GCLogSeverity {
+    match level {
+        Level::Error => GCLogSeverity::Error,
+        Level::Warn => GCLogSeverity::Warning,
+        Level::Info => GCLogSeverity::Info,
+        Level::Debug => GCLogSeverity::Debug,
+        Level::Trace => GCLogSeverity::Default,
+    }
+}
@@ -0,0 +1,50 @@
+//! Auto-detection of the [`GCMonitoredResource`] for the environment the binary is
+//! running in, so log entries written programmatically are attributed to the right
+//! resource in the Logs Explorer instead of defaulting to `global`.
+//!
+//! Requires the `detect-resource` feature.
+
+use crate::GCMonitoredResource;
+use std::time::Duration;
+
+const METADATA_BASE: &str = "http://metadata.google.internal/computeMetadata/v1/";
+
+/// Detects the [`GCMonitoredResource`] for the running environment.
+///
+/// Queries the GCE metadata server for the project id, instance id and zone to build a
+/// `gce_instance` resource. If the metadata server can't be reached (for example, when
+/// not running on GCP) this falls back to a `global` resource carrying just the project
+/// id, which may itself be empty.
+pub fn detect_resource() -> GCMonitoredResource<'static> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .unwrap_or_default();
+
+    let project_id = fetch_metadata(&client, "project/project-id").unwrap_or_default();
+
+    match (
+        fetch_metadata(&client, "instance/id"),
+        fetch_metadata(&client, "instance/zone"),
+    ) {
+        (Some(instance_id), Some(zone)) => {
+            // The zone comes back as `projects/<num>/zones/<zone>`; we only want the
+            // last path segment.
+            let zone = zone.rsplit('/').next().unwrap_or(&zone).to_owned();
+            GCMonitoredResource::gce_instance(project_id, instance_id, zone)
+        }
+        _ => GCMonitoredResource::global(project_id),
+    }
+}
+
+fn fetch_metadata(client: &reqwest::blocking::Client, path: &str) -> Option<String> {
+    client
+        .get(format!("{METADATA_BASE}{path}"))
+        .header("Metadata-Flavor", "Google")
+        .send()
+        .ok()?
+        .error_for_status()
+        .ok()?
+        .text()
+        .ok()
+}
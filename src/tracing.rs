@@ -0,0 +1,137 @@
+//! A [`tracing_subscriber::Layer`] that emits [`GoogleCloudStructLog`] entries, folding
+//! span fields into `logging.googleapis.com/labels` and extracting the current span's
+//! trace/span IDs into the `trace`/`spanId` fields.
+//!
+//! Requires the `tracing` feature.
+
+use crate::{GCLogSeverity, GCPayload, GCSourceLocation, GoogleCloudStructLog};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Record};
+use tracing::{Event, Id, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Emits one [`GoogleCloudStructLog`] JSON line per `tracing` event.
+///
+/// `project_id` is used to build the `logging.googleapis.com/trace` resource name
+/// (`projects/<project_id>/traces/<trace_id>`).
+pub struct GoogleCloudLoggingLayer {
+    project_id: String,
+}
+
+impl GoogleCloudLoggingLayer {
+    /// Creates a layer that attributes traces to `project_id`.
+    pub fn new(project_id: impl Into<String>) -> Self {
+        Self {
+            project_id: project_id.into(),
+        }
+    }
+}
+
+struct SpanFields(HashMap<String, String>);
+
+struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_owned(), format!("{value:?}"));
+    }
+}
+
+struct EventVisitor<'a> {
+    labels: &'a mut HashMap<String, String>,
+    message: &'a mut Option<String>,
+}
+
+impl Visit for EventVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            *self.message = Some(format!("{value:?}"));
+        } else {
+            self.labels.insert(field.name().to_owned(), format!("{value:?}"));
+        }
+    }
+}
+
+impl<S> Layer<S> for GoogleCloudLoggingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("span must exist in on_new_span");
+        let mut fields = HashMap::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        span.extensions_mut().insert(SpanFields(fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("span must exist in on_record");
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            values.record(&mut FieldVisitor(fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut labels = HashMap::new();
+        let mut message = None;
+        event.record(&mut EventVisitor {
+            labels: &mut labels,
+            message: &mut message,
+        });
+
+        // The root span's id is shared by every span in the call tree and becomes the
+        // `trace` identifier, so Cloud Logging can correlate lines across spans. The
+        // leaf (current) span's id is specific to this event and becomes `spanId`.
+        let mut root_span_id = None;
+        let mut leaf_span_id = None;
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                    labels.extend(fields.clone());
+                }
+                root_span_id.get_or_insert_with(|| span.id());
+                leaf_span_id = Some(span.id());
+            }
+        }
+
+        let trace = root_span_id
+            .map(|id| format!("projects/{}/traces/{:032x}", self.project_id, id.into_u64()));
+        let span_id = leaf_span_id.map(|id| format!("{:016x}", id.into_u64()));
+
+        let metadata = event.metadata();
+        let entry = GoogleCloudStructLog {
+            severity: Some(severity_for(*metadata.level())),
+            payload: message.map(GCPayload::<()>::Message),
+            labels,
+            source_location: Some(GCSourceLocation {
+                file: metadata.file(),
+                line: metadata.line().map(|line| line.to_string()),
+                function: Some(metadata.target()),
+            }),
+            span_id,
+            trace,
+            time: Some(Utc::now()),
+            ..Default::default()
+        };
+
+        println!(
+            "{}",
+            serde_json::to_string(&entry).expect("GoogleCloudStructLog always serializes")
+        );
+    }
+}
+
+fn severity_for(level: Level) -> GCLogSeverity {
+    match level {
+        Level::ERROR => GCLogSeverity::Error,
+        Level::WARN => GCLogSeverity::Warning,
+        Level::INFO => GCLogSeverity::Info,
+        Level::DEBUG => GCLogSeverity::Debug,
+        Level::TRACE => GCLogSeverity::Default,
+    }
+}
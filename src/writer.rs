@@ -0,0 +1,275 @@
+//! A batching writer that ships [`GoogleCloudStructLog`] entries to the Cloud Logging
+//! [`entries:write`](https://cloud.google.com/logging/docs/reference/v2/rest/v2/entries/write) API.
+//!
+//! This is an alternative to printing JSON to stdout for an agent to scrape: the [`Writer`]
+//! buffers entries in memory and flushes them to the API either when the queue reaches
+//! [`WriterConfig::max_batch_size`] or after [`WriterConfig::flush_interval`], whichever
+//! comes first. Flushes are retried with exponential backoff on transient failures (429,
+//! 5xx and request errors), up to [`WriterConfig::max_retries`] times; after that, or on any
+//! other error status, the batch is dropped. If no access token can be fetched, the flush
+//! stops without retrying and the batch stays queued for the next flush.
+//!
+//! Requires the `writer` feature.
+
+use crate::{GCMonitoredResource, GoogleCloudStructLog};
+use serde::Serialize;
+use std::fmt;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const ENTRIES_WRITE_URL: &str = "https://logging.googleapis.com/v2/entries:write";
+const METADATA_TOKEN_URL: &str = "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+/// Configuration for a [`Writer`].
+#[derive(Clone, Debug)]
+pub struct WriterConfig {
+    /// The full log name, e.g. `projects/my-project/logs/my-log`.
+    pub log_name: String,
+    /// The monitored resource that entries written through this writer are associated with.
+    pub resource: GCMonitoredResource<'static>,
+    /// Flush once this many entries have been queued.
+    pub max_batch_size: usize,
+    /// Flush at most this long after the first entry in a batch was queued.
+    pub flush_interval: Duration,
+    /// Maximum number of retry attempts for a batch before it is dropped.
+    pub max_retries: u32,
+}
+
+impl Default for WriterConfig {
+    fn default() -> Self {
+        Self {
+            log_name: String::new(),
+            resource: GCMonitoredResource::global(String::new()),
+            max_batch_size: 100,
+            flush_interval: Duration::from_secs(5),
+            max_retries: 5,
+        }
+    }
+}
+
+/// Ships [`GoogleCloudStructLog`] entries to the `entries:write` API in the background.
+///
+/// Cloning is not supported; share a [`Writer`] behind an `Arc` if multiple producers need it.
+pub struct Writer {
+    sender: Sender<Command>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+enum Command {
+    Entry(Box<GoogleCloudStructLog<'static>>),
+    Flush(Sender<()>),
+    Shutdown,
+}
+
+impl Writer {
+    /// Spawns the background thread that batches and ships log entries.
+    pub fn new(config: WriterConfig) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let handle = thread::spawn(move || run(config, receiver));
+        Self {
+            sender,
+            handle: Some(handle),
+        }
+    }
+
+    /// Queues an entry to be written on the next flush.
+    ///
+    /// This never blocks on network I/O; it only hands the entry to the background thread.
+    pub fn log(&self, entry: GoogleCloudStructLog<'static>) {
+        // The background thread only goes away via `Drop`, so the channel stays open for
+        // the lifetime of the `Writer`.
+        let _ = self.sender.send(Command::Entry(Box::new(entry)));
+    }
+
+    /// Blocks until every entry queued so far has been flushed (or permanently failed).
+    /// If no access token can be fetched, this returns with the entries still queued.
+    pub fn flush(&self) {
+        let (done_tx, done_rx) = mpsc::channel();
+        if self.sender.send(Command::Flush(done_tx)).is_ok() {
+            let _ = done_rx.recv();
+        }
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        self.flush();
+        let _ = self.sender.send(Command::Shutdown);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EntriesWriteRequest<'a> {
+    #[serde(rename = "logName")]
+    log_name: &'a str,
+    resource: &'a GCMonitoredResource<'static>,
+    entries: &'a [GoogleCloudStructLog<'static>],
+}
+
+fn run(config: WriterConfig, receiver: mpsc::Receiver<Command>) {
+    let client = reqwest::blocking::Client::new();
+    let mut token_cache = TokenCache::default();
+    let mut batch = Vec::with_capacity(config.max_batch_size);
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let timeout = deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+            .unwrap_or(config.flush_interval);
+
+        match receiver.recv_timeout(timeout) {
+            Ok(Command::Entry(entry)) => {
+                if batch.is_empty() {
+                    deadline = Some(Instant::now() + config.flush_interval);
+                }
+                batch.push(*entry);
+                if batch.len() >= config.max_batch_size {
+                    flush_batch(&config, &client, &mut token_cache, &mut batch);
+                    deadline = None;
+                }
+            }
+            Ok(Command::Flush(done)) => {
+                flush_batch(&config, &client, &mut token_cache, &mut batch);
+                deadline = None;
+                let _ = done.send(());
+            }
+            Ok(Command::Shutdown) => {
+                flush_batch(&config, &client, &mut token_cache, &mut batch);
+                return;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                flush_batch(&config, &client, &mut token_cache, &mut batch);
+                deadline = None;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                flush_batch(&config, &client, &mut token_cache, &mut batch);
+                return;
+            }
+        }
+    }
+}
+
+fn flush_batch(
+    config: &WriterConfig,
+    client: &reqwest::blocking::Client,
+    token_cache: &mut TokenCache,
+    batch: &mut Vec<GoogleCloudStructLog<'static>>,
+) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let mut backoff = Duration::from_millis(500);
+    for attempt in 0..=config.max_retries {
+        let token = match token_cache.get(client) {
+            Ok(token) => token,
+            Err(err) => {
+                eprintln!("google_cloud_logging: failed to fetch access token: {err}");
+                return;
+            }
+        };
+
+        let body = EntriesWriteRequest {
+            log_name: &config.log_name,
+            resource: &config.resource,
+            entries: batch,
+        };
+
+        let result = client
+            .post(ENTRIES_WRITE_URL)
+            .bearer_auth(token)
+            .json(&body)
+            .send();
+
+        match result {
+            Ok(response) if response.status().is_success() => {
+                batch.clear();
+                return;
+            }
+            Ok(response) if is_retryable(response.status().as_u16()) && attempt < config.max_retries => {
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+            Ok(response) => {
+                eprintln!(
+                    "google_cloud_logging: entries:write failed with status {}, dropping batch of {} entries",
+                    response.status(),
+                    batch.len()
+                );
+                batch.clear();
+                return;
+            }
+            Err(err) if attempt < config.max_retries => {
+                eprintln!("google_cloud_logging: entries:write request error, retrying: {err}");
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+            Err(err) => {
+                eprintln!(
+                    "google_cloud_logging: entries:write failed after {} attempts, dropping batch of {} entries: {err}",
+                    attempt + 1,
+                    batch.len()
+                );
+                batch.clear();
+                return;
+            }
+        }
+    }
+}
+
+fn is_retryable(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+#[derive(Default)]
+struct TokenCache {
+    cached: Option<(String, Instant)>,
+}
+
+#[derive(serde::Deserialize)]
+struct MetadataTokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+impl TokenCache {
+    fn get(&mut self, client: &reqwest::blocking::Client) -> Result<String, TokenError> {
+        if let Some((token, expires_at)) = &self.cached {
+            // Refresh a little before actual expiry to avoid racing the metadata server.
+            if Instant::now() + Duration::from_secs(30) < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+
+        let response: MetadataTokenResponse = client
+            .get(METADATA_TOKEN_URL)
+            .header("Metadata-Flavor", "Google")
+            .send()
+            .map_err(TokenError::Request)?
+            .json()
+            .map_err(TokenError::Request)?;
+
+        let expires_at = Instant::now() + Duration::from_secs(response.expires_in);
+        self.cached = Some((response.access_token.clone(), expires_at));
+        Ok(response.access_token)
+    }
+}
+
+#[derive(Debug)]
+enum TokenError {
+    Request(reqwest::Error),
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::Request(err) => write!(f, "could not fetch token from metadata server: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}